@@ -2,11 +2,21 @@
 
 use std::collections::BTreeMap;
 
-use engine_traits::{CompactExt, CompactedEvent, Result};
+use engine_traits::{CFNamesExt, CompactExt, CompactedEvent, Range, Result};
 
 use crate::engine::AgateEngine;
 
-// TODO: Implement these for AgateDB.
+/// One compacted key span recorded by `compact_range`/`compact_files_in_range_cf`,
+/// along with its CF size before and after the compaction ran.
+#[derive(Clone, Debug)]
+pub struct CompactionDecline {
+    pub(crate) cf: String,
+    pub(crate) start_key: Vec<u8>,
+    pub(crate) end_key: Vec<u8>,
+    pub(crate) bytes_before: u64,
+    pub(crate) bytes_after: u64,
+}
+
 impl CompactExt for AgateEngine {
     type CompactedEvent = AgateCompactedEvent;
 
@@ -19,18 +29,21 @@ impl CompactExt for AgateEngine {
         cf: &str,
         start_key: Option<&[u8]>,
         end_key: Option<&[u8]>,
-        exclusive_manual: bool,
-        max_subcompactions: u32,
+        _exclusive_manual: bool,
+        _max_subcompactions: u32,
     ) -> Result<()> {
-        Ok(())
+        self.compact_range_cf_and_record(cf, start_key, end_key)
     }
 
     fn compact_files_in_range(
         &self,
         start: Option<&[u8]>,
         end: Option<&[u8]>,
-        output_level: Option<i32>,
+        _output_level: Option<i32>,
     ) -> Result<()> {
+        for cf in self.cf_names() {
+            self.compact_range_cf_and_record(cf, start, end)?;
+        }
         Ok(())
     }
 
@@ -39,33 +52,105 @@ impl CompactExt for AgateEngine {
         cf: &str,
         start: Option<&[u8]>,
         end: Option<&[u8]>,
-        output_level: Option<i32>,
+        _output_level: Option<i32>,
     ) -> Result<()> {
-        Ok(())
+        self.compact_range_cf_and_record(cf, start, end)
     }
 
     fn compact_files_cf(
         &self,
         cf: &str,
-        files: Vec<String>,
-        output_level: Option<i32>,
-        max_subcompactions: u32,
-        exclude_l0: bool,
+        _files: Vec<String>,
+        _output_level: Option<i32>,
+        _max_subcompactions: u32,
+        _exclude_l0: bool,
+    ) -> Result<()> {
+        self.compact_range_cf_and_record(cf, None, None)
+    }
+}
+
+impl AgateEngine {
+    /// Triggers compaction of `cf` over `[start_key, end_key)` and records the CF's
+    /// size before and after, so a later `AgateCompactedEvent` can report real declined
+    /// bytes to the split checker.
+    ///
+    /// TODO: AgateDB doesn't yet expose a manual range-compaction entry point to this
+    /// engine, so the only real reclamation work done here is GC'ing `cf`'s range
+    /// tombstones (see `tombstone::gc_tombstones_cf`); once a real trigger lands
+    /// upstream, call it between the two size snapshots below too. That's also why
+    /// the two snapshots below are taken with `cf_size_over_range`'s tombstone-unaware
+    /// scan rather than `table_properties_collection`: the latter already excludes
+    /// whatever `gc_tombstones_cf` is about to reclaim, which would make
+    /// `bytes_before` and `bytes_after` identical.
+    fn compact_range_cf_and_record(
+        &self,
+        cf: &str,
+        start_key: Option<&[u8]>,
+        end_key: Option<&[u8]>,
     ) -> Result<()> {
+        let start_key = start_key.unwrap_or(&[]);
+        let end_key = end_key.unwrap_or(&[]);
+        let range = Range::new(start_key, end_key);
+
+        let bytes_before = self.cf_size_over_range(cf, &range)?;
+
+        self.gc_tombstones_cf(cf)?;
+
+        let bytes_after = self.cf_size_over_range(cf, &range)?;
+
+        self.compaction_declines
+            .lock()
+            .unwrap()
+            .push(CompactionDecline {
+                cf: cf.to_string(),
+                start_key: start_key.to_vec(),
+                end_key: end_key.to_vec(),
+                bytes_before,
+                bytes_after,
+            });
+
         Ok(())
     }
+
+    /// Measures `cf`'s physical size over `range` straight off the underlying agate
+    /// transaction (via `raw_bytes_in_range`), not through `table_properties_collection`,
+    /// which scans via the tombstone-aware `Iterable` path and so never sees the keys
+    /// `gc_tombstones_cf` is about to reclaim.
+    fn cf_size_over_range(&self, cf: &str, range: &Range<'_>) -> Result<u64> {
+        Ok(self.raw_bytes_in_range(cf, range.start_key, range.end_key))
+    }
+
+    /// Drains the compaction declines recorded so far into a fresh `AgateCompactedEvent`
+    /// for `cf`, so callers (e.g. raftstore's split checker) can attribute them to
+    /// regions via `CompactedEvent::calc_ranges_declined_bytes`.
+    pub fn take_compacted_event(&self, cf: &str) -> AgateCompactedEvent {
+        let mut declines = self.compaction_declines.lock().unwrap();
+        let (mine, rest): (Vec<CompactionDecline>, Vec<CompactionDecline>) =
+            declines.drain(..).partition(|d| d.cf == cf);
+        *declines = rest;
+
+        AgateCompactedEvent {
+            cf: cf.to_string(),
+            declines: mine,
+        }
+    }
 }
 
-pub struct AgateCompactedEvent;
+pub struct AgateCompactedEvent {
+    cf: String,
+    declines: Vec<CompactionDecline>,
+}
 
-// TODO: Make size declining not trivial AgateDB.
 impl CompactedEvent for AgateCompactedEvent {
     fn total_bytes_declined(&self) -> u64 {
-        0
+        self.declines
+            .iter()
+            .map(|d| d.bytes_before.saturating_sub(d.bytes_after))
+            .sum()
     }
 
     fn is_size_declining_trivial(&self, split_check_diff: u64) -> bool {
-        true
+        self.total_bytes_declined() < split_check_diff
     }
 
     fn output_level_label(&self) -> String {
@@ -77,10 +162,38 @@ impl CompactedEvent for AgateCompactedEvent {
         ranges: &BTreeMap<Vec<u8>, u64>,
         bytes_threshold: u64,
     ) -> Vec<(u64, u64)> {
-        vec![]
+        let mut region_declined_bytes: BTreeMap<u64, u64> = BTreeMap::new();
+
+        for decline in &self.declines {
+            let bytes_declined = decline.bytes_before.saturating_sub(decline.bytes_after);
+            if bytes_declined == 0 {
+                continue;
+            }
+
+            // `ranges` maps each region's (exclusive) end key to its region ID in
+            // ascending key order. Starting from the first end key strictly after the
+            // compacted span's start finds the region owning that start key; walking
+            // forward from there covers every region the compacted span overlaps.
+            let after_start = (
+                std::ops::Bound::Excluded(decline.start_key.clone()),
+                std::ops::Bound::Unbounded,
+            );
+            for (region_end_key, region_id) in ranges.range::<Vec<u8>, _>(after_start) {
+                *region_declined_bytes.entry(*region_id).or_insert(0) += bytes_declined;
+
+                if decline.end_key.is_empty() || region_end_key.as_slice() >= decline.end_key.as_slice() {
+                    break;
+                }
+            }
+        }
+
+        region_declined_bytes
+            .into_iter()
+            .filter(|(_, bytes)| *bytes >= bytes_threshold)
+            .collect()
     }
 
     fn cf(&self) -> &str {
-        "AgateCompactedEvent"
+        &self.cf
     }
 }
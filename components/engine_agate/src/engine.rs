@@ -4,20 +4,23 @@ use std::{
     collections::HashSet,
     iter::FromIterator,
     path::{self, Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use agatedb::{Agate, AgateIterator, AgateOptions, IteratorOptions};
 use bytes::Bytes;
 use engine_traits::{
     Error, IterOptions, Iterable, Iterator, KvEngine, MiscExt, Peekable, RaftEngine, ReadOptions,
-    Result, SeekKey, SyncMutable, TabletAccessor, WriteOptions, CF_DEFAULT,
+    Result, SeekKey, SyncMutable, TabletAccessor, WriteBatchExt, WriteOptions, CF_DEFAULT,
 };
 
 use crate::{
+    compact::CompactionDecline,
     db_vector::AgateDBVector,
     snapshot::AgateSnapshot,
-    utils::{add_cf_prefix, get_cf_and_key},
+    tombstone::TombstoneList,
+    txn_config::TxnConfig,
+    utils::{add_cf_prefix, get_cf_and_key, KeyComparator},
     write_batch::AgateWriteBatch,
 };
 
@@ -26,10 +29,41 @@ pub struct AgateEngine {
     pub(crate) agate: Arc<Agate>,
     pub(crate) cf_names: HashSet<String>,
     pub(crate) path: PathBuf,
+    // Declines recorded by `compact_range`/`compact_files_in_range_cf` since the last
+    // time they were handed off to an `AgateCompactedEvent`.
+    pub(crate) compaction_declines: Arc<Mutex<Vec<CompactionDecline>>>,
+    pub(crate) comparator: KeyComparator,
+    // Range-tombstone markers recorded by `delete_ranges_cf_tombstone(.., use_tombstone: true)`,
+    // consulted by point reads and iterators and reclaimed lazily by `gc_tombstones_cf`.
+    pub(crate) tombstones: TombstoneList,
+    pub(crate) txn_config: TxnConfig,
 }
 
 impl AgateEngine {
     pub fn new(path: &Path, cfs: Vec<String>) -> Self {
+        Self::new_with_comparator(path, cfs, KeyComparator::default())
+    }
+
+    /// Like `new`, but registers `comparator` for the iterator bound checks, key-equality
+    /// decisions and range checks this crate makes, instead of plain byte comparison.
+    ///
+    /// TODO: The underlying `agate` instance still stores and walks keys in plain byte
+    /// order; `comparator` only governs bound checks and equality decisions made in
+    /// this crate (`valid`, `delete_range`, `seek_for_prev`). Making AgateDB's own
+    /// storage order follow a custom comparator would need changes in `agatedb` itself.
+    pub fn new_with_comparator(path: &Path, cfs: Vec<String>, comparator: KeyComparator) -> Self {
+        Self::new_with_txn_config(path, cfs, comparator, TxnConfig::default())
+    }
+
+    /// Like `new_with_comparator`, but also registers `txn_config` (see `TxnConfig` for
+    /// which of its fields change behavior) as the default for this engine's write
+    /// batches.
+    pub fn new_with_txn_config(
+        path: &Path,
+        cfs: Vec<String>,
+        comparator: KeyComparator,
+        txn_config: TxnConfig,
+    ) -> Self {
         let mut agate_opts = AgateOptions {
             dir: path.to_path_buf(),
             value_dir: path.to_path_buf(),
@@ -40,6 +74,10 @@ impl AgateEngine {
             agate: Arc::new(agate_opts.open().unwrap()),
             cf_names: HashSet::from_iter([vec![CF_DEFAULT.to_string()], cfs].concat().into_iter()),
             path: path.to_path_buf(),
+            compaction_declines: Arc::new(Mutex::new(Vec::new())),
+            comparator,
+            tombstones: TombstoneList::default(),
+            txn_config,
         }
     }
 
@@ -50,6 +88,79 @@ impl AgateEngine {
             Ok(())
         }
     }
+
+    /// Opens a new staged write batch, modeled on RocksDB's transaction bridge: stage
+    /// mutations on the returned batch (optionally marking nested savepoints with
+    /// `AgateWriteBatch::set_savepoint`/`rollback_to_savepoint`/`pop_savepoint`), then
+    /// call `commit` to flush them all in a single `agate` transaction or `rollback` to
+    /// discard them.
+    pub fn begin_txn(&self) -> AgateWriteBatch {
+        self.write_batch()
+    }
+
+    /// Like `write_batch`/`WriteBatchExt::write_batch`, but commits under
+    /// `txn_config` instead of `self.txn_config`. Use this to open, say, a pessimistic
+    /// batch against an engine that otherwise defaults to optimistic commits.
+    pub fn write_batch_opt(&self, txn_config: TxnConfig) -> AgateWriteBatch {
+        AgateWriteBatch::new_with_txn_config(self.agate.clone(), txn_config)
+    }
+
+    /// Opens a transaction honoring `self.txn_config`, in place of calling
+    /// `self.agate.new_transaction` directly.
+    ///
+    /// TODO: `agatedb::Agate::new_transaction` only takes the read/write `update` flag;
+    /// it doesn't yet expose a pessimistic-mode or deadlock-detection entry point for
+    /// this engine to forward `txn_config.mode`/`txn_config.deadlock_detect` into. Once
+    /// it does, branch on them here instead of always opening the default
+    /// (optimistic, no deadlock detection) transaction agate constructs today.
+    pub(crate) fn new_transaction(&self, update: bool) -> agatedb::Transaction {
+        self.agate.new_transaction(update)
+    }
+
+    /// Sums `(key.len() + value.len())` over every key physically present in `cf`
+    /// within `[begin_key, end_key)`, reading straight off a fresh `agate` transaction
+    /// instead of going through `Iterable::iterator_cf_opt`/`scan_cf`. Those route
+    /// through `AgateEngineIterator::skip_tombstoned`, which hides exactly the keys
+    /// callers like `flow_control_factors::get_cf_pending_compaction_bytes` and
+    /// `compact::cf_size_over_range` need to measure: ones still on disk under a live
+    /// range tombstone.
+    pub(crate) fn raw_bytes_in_range(&self, cf: &str, begin_key: &[u8], end_key: &[u8]) -> u64 {
+        let mut txn = self.new_transaction(false);
+
+        // An empty `end_key` means "unbounded", not "the bare CF prefix": prefixing it
+        // would yield a value that sorts before every real key in the CF (there's no
+        // key suffix after the prefix), so the upper-bound check below would fire on
+        // the very first key. Track that case instead and rely solely on the
+        // `cf_name != cf` break to stop at the CF's end.
+        let end_is_unbounded = end_key.is_empty();
+
+        let begin_key = add_cf_prefix(begin_key, Some(cf.to_string()));
+        let end_key = add_cf_prefix(end_key, Some(cf.to_string()));
+
+        let mut iter = txn.new_iterator(&IteratorOptions::default());
+        iter.seek(&Bytes::from(begin_key.clone()));
+
+        let mut bytes = 0u64;
+        while iter.valid() {
+            let full_key = iter.item().key();
+
+            let (cf_name, _) = get_cf_and_key(full_key);
+            if cf_name != cf {
+                break;
+            }
+            if !begin_key.is_empty() && full_key < &begin_key[..] {
+                break;
+            }
+            if !end_is_unbounded && full_key >= &end_key[..] {
+                break;
+            }
+
+            bytes += (full_key.len() + iter.item().value().len()) as u64;
+            iter.next();
+        }
+
+        bytes
+    }
 }
 
 impl KvEngine for AgateEngine {
@@ -80,9 +191,13 @@ impl Peekable for AgateEngine {
     type DBVector = AgateDBVector;
 
     fn get_value_opt(&self, opts: &ReadOptions, key: &[u8]) -> Result<Option<Self::DBVector>> {
+        if self.tombstones.is_covered(CF_DEFAULT, key) {
+            return Ok(None);
+        }
+
         let key = add_cf_prefix(key, None);
 
-        let mut txn = self.agate.new_transaction(false);
+        let mut txn = self.new_transaction(false);
 
         match txn.get(&Bytes::from(key)) {
             Ok(item) => Ok(Some(AgateDBVector::from_raw(item.value()))),
@@ -100,9 +215,13 @@ impl Peekable for AgateEngine {
     ) -> Result<Option<Self::DBVector>> {
         self.check_cf_exist(cf)?;
 
+        if self.tombstones.is_covered(cf, key) {
+            return Ok(None);
+        }
+
         let key = add_cf_prefix(key, Some(cf.to_string()));
 
-        let mut txn = self.agate.new_transaction(false);
+        let mut txn = self.new_transaction(false);
 
         match txn.get(&Bytes::from(key)) {
             Ok(item) => Ok(Some(AgateDBVector::from_raw(item.value()))),
@@ -118,7 +237,7 @@ impl SyncMutable for AgateEngine {
     fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
         let key = add_cf_prefix(key, None);
 
-        let mut txn = self.agate.new_transaction(true);
+        let mut txn = self.new_transaction(true);
         txn.set(Bytes::from(key), Bytes::copy_from_slice(value))
             .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
         txn.commit()
@@ -130,7 +249,7 @@ impl SyncMutable for AgateEngine {
 
         let key = add_cf_prefix(key, Some(cf.to_string()));
 
-        let mut txn = self.agate.new_transaction(true);
+        let mut txn = self.new_transaction(true);
         txn.set(Bytes::from(key), Bytes::copy_from_slice(value))
             .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
         txn.commit()
@@ -140,7 +259,7 @@ impl SyncMutable for AgateEngine {
     fn delete(&self, key: &[u8]) -> Result<()> {
         let key = add_cf_prefix(key, None);
 
-        let mut txn = self.agate.new_transaction(true);
+        let mut txn = self.new_transaction(true);
         txn.delete(Bytes::from(key))
             .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
         txn.commit()
@@ -152,7 +271,7 @@ impl SyncMutable for AgateEngine {
 
         let key = add_cf_prefix(key, Some(cf.to_string()));
 
-        let mut txn = self.agate.new_transaction(true);
+        let mut txn = self.new_transaction(true);
         txn.delete(Bytes::from(key))
             .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
         txn.commit()
@@ -160,13 +279,13 @@ impl SyncMutable for AgateEngine {
     }
 
     fn delete_range(&self, begin_key: &[u8], end_key: &[u8]) -> Result<()> {
-        if end_key < begin_key {
+        if (self.comparator.cmp)(end_key, begin_key) == std::cmp::Ordering::Less {
             return Err(engine_traits::Error::Engine(
                 "end_key < begin_key".to_string(),
             ));
         }
 
-        let mut txn = self.agate.new_transaction(true);
+        let mut txn = self.new_transaction(true);
 
         self.scan(begin_key, end_key, false, |key, _| {
             let key = &add_cf_prefix(key, None);
@@ -181,7 +300,7 @@ impl SyncMutable for AgateEngine {
     }
 
     fn delete_range_cf(&self, cf: &str, begin_key: &[u8], end_key: &[u8]) -> Result<()> {
-        if end_key < begin_key {
+        if (self.comparator.cmp)(end_key, begin_key) == std::cmp::Ordering::Less {
             return Err(engine_traits::Error::Engine(
                 "end_key < begin_key".to_string(),
             ));
@@ -189,7 +308,7 @@ impl SyncMutable for AgateEngine {
 
         self.check_cf_exist(cf)?;
 
-        let mut txn = self.agate.new_transaction(true);
+        let mut txn = self.new_transaction(true);
 
         self.scan_cf(cf, begin_key, end_key, false, |key, _| {
             let key = add_cf_prefix(key, Some(cf.to_string()));
@@ -208,71 +327,140 @@ impl Iterable for AgateEngine {
     type Iterator = AgateEngineIterator;
 
     fn iterator_opt(&self, opts: IterOptions) -> Result<Self::Iterator> {
-        let txn = self.agate.new_transaction(false);
+        let txn = self.new_transaction(false);
 
         let iter = txn.new_iterator(&IteratorOptions::default());
 
         Ok(AgateEngineIterator {
+            txn,
             iter,
+            reverse: false,
             opts,
             cf_name: None,
+            comparator: self.comparator.clone(),
+            tombstones: self.tombstones.clone(),
         })
     }
     fn iterator_cf_opt(&self, cf: &str, opts: IterOptions) -> Result<Self::Iterator> {
         self.check_cf_exist(cf)?;
 
-        let txn = self.agate.new_transaction(false);
+        let txn = self.new_transaction(false);
 
         let iter = txn.new_iterator(&IteratorOptions::default());
 
         Ok(AgateEngineIterator {
+            txn,
             iter,
+            reverse: false,
             opts,
             cf_name: Some(cf.to_owned()),
+            comparator: self.comparator.clone(),
+            tombstones: self.tombstones.clone(),
         })
     }
 }
 
 pub struct AgateEngineIterator {
+    pub(crate) txn: agatedb::Transaction,
     pub(crate) iter: agatedb::Iterator,
+    // Whether `iter` was opened with `IteratorOptions { reverse: true, .. }`. `SeekKey::End`
+    // and a missed `seek_for_prev` open a reverse-mode iterator on demand so they land on
+    // the target in O(log n) instead of walking the whole CF range with `next()`.
+    pub(crate) reverse: bool,
     pub(crate) opts: IterOptions,
     pub(crate) cf_name: Option<String>,
+    pub(crate) comparator: KeyComparator,
+    pub(crate) tombstones: TombstoneList,
+}
+
+impl AgateEngineIterator {
+    fn switch_direction(&mut self, reverse: bool) {
+        if self.reverse != reverse {
+            self.iter = self.txn.new_iterator(&IteratorOptions {
+                reverse,
+                ..Default::default()
+            });
+            self.reverse = reverse;
+        }
+    }
+
+    /// Steps past every key the engine's tombstones cover, in whichever physical
+    /// direction `physical_next` requests (`true` for `self.iter.next()`, `false` for
+    /// `self.iter.prev()`), so callers never observe a key that
+    /// `delete_ranges_cf_tombstone(.., use_tombstone: true)` has marked as deleted even
+    /// though it's still physically on disk.
+    ///
+    /// The direction has to come from the caller rather than from `self.reverse`:
+    /// `next()`/`prev()` below sometimes step with `iter.prev()` even while
+    /// `self.reverse` is false (and vice versa), to turn a single-direction `agate`
+    /// iterator into a bidirectional one. Deriving the continuation direction from
+    /// `self.reverse` alone would walk the wrong way whenever that happens.
+    fn skip_tombstoned(&mut self, physical_next: bool) -> Result<bool> {
+        loop {
+            if !self.raw_valid()? {
+                return Ok(false);
+            }
+
+            let cf = self.cf_name.as_deref().unwrap_or(CF_DEFAULT);
+            if !self.tombstones.is_covered(cf, self.key()) {
+                return Ok(true);
+            }
+
+            if physical_next {
+                self.iter.next();
+            } else {
+                self.iter.prev();
+            }
+
+            if !self.iter.valid() {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// The smallest key that sorts after every key carrying the current CF prefix, so
+    /// reverse-seeking to it lands on the CF's last key directly.
+    fn cf_upper_bound(&self) -> Vec<u8> {
+        let mut upper = add_cf_prefix(&[], self.cf_name.clone());
+        while let Some(&last) = upper.last() {
+            if last == 0xFF {
+                upper.pop();
+            } else {
+                *upper.last_mut().unwrap() += 1;
+                return upper;
+            }
+        }
+        vec![0xFF; 256]
+    }
 }
 
 impl Iterator for AgateEngineIterator {
     fn seek(&mut self, key: SeekKey<'_>) -> Result<bool> {
         match key {
             SeekKey::Start => {
+                self.switch_direction(false);
                 self.iter
                     .seek(&Bytes::from(add_cf_prefix(&[], self.cf_name.clone())));
 
-                self.valid()
+                self.skip_tombstoned(true)
             }
             SeekKey::End => {
-                let seek_result = self.seek(SeekKey::Start)?;
-
-                // No such key found.
-                if !seek_result {
-                    return Ok(false);
-                }
-
-                assert!(self.valid()?);
-                let mut last_key = vec![];
-
-                while self.valid()? {
-                    let key = self.key();
-                    last_key.clear();
-                    last_key.extend_from_slice(key);
-                    self.next();
-                }
-
-                self.seek(SeekKey::Key(&last_key))
+                // Native reverse iteration: seek straight to the CF's upper bound on a
+                // reverse-mode iterator, which lands on the last key in O(log n) rather
+                // than walking every key in the CF with repeated `next()` calls.
+                self.switch_direction(true);
+                let upper = self.cf_upper_bound();
+                self.iter.seek(&Bytes::from(upper));
+
+                // In reverse mode, stepping toward smaller keys is `iter.next()`.
+                self.skip_tombstoned(true)
             }
             SeekKey::Key(key) => {
+                self.switch_direction(false);
                 self.iter
                     .seek(&Bytes::from(add_cf_prefix(key, self.cf_name.clone())));
 
-                self.valid()
+                self.skip_tombstoned(true)
             }
         }
     }
@@ -282,18 +470,39 @@ impl Iterator for AgateEngineIterator {
             SeekKey::Start => self.seek(SeekKey::Start),
             SeekKey::End => self.seek(SeekKey::End),
             SeekKey::Key(key) => {
-                let valid = self.seek(SeekKey::Key(key))?;
-
-                if !valid {
-                    // TODO: Consider exist_key < seek_key < upper_bound_key.
-                    return self.seek_to_last();
-                }
+                // Forward-seek to the first key >= `key`, then decide whether that's
+                // already the target via `comparator.keys_equal` rather than raw byte
+                // equality -- a comparator with `diff_bytes_can_equal = true` can have
+                // two byte-distinct keys compare equal, in which case landing on either
+                // one already satisfies `seek_for_prev` and no `prev()` is needed.
+                //
+                // TODO: `agate`'s own storage order is still plain bytewise (see the
+                // TODO on `new_with_comparator`), so for a non-bytewise `comparator` the
+                // forward seek below may not land where `cmp` would call ">= key", and
+                // the `prev()` fallback steps to the physically preceding key rather
+                // than the `cmp`-preceding one. `keys_equal` fixes the equality half of
+                // this; true custom-ordered storage would need `agatedb` itself to take
+                // a comparator.
+                self.switch_direction(false);
+                self.iter
+                    .seek(&Bytes::from(add_cf_prefix(key, self.cf_name.clone())));
 
-                if self.key() != key {
-                    self.prev();
+                let on_target = self.raw_valid()? && self.comparator.keys_equal(self.key(), key);
+                if !on_target {
+                    if self.iter.valid() {
+                        self.iter.prev();
+                    } else {
+                        // Nothing >= `key` exists anywhere in the engine; the CF's own
+                        // last key (if any) is the greatest one <= `key`.
+                        self.switch_direction(true);
+                        let upper = self.cf_upper_bound();
+                        self.iter.seek(&Bytes::from(upper));
+                    }
                 }
 
-                self.valid()
+                // Keep walking toward smaller keys: `iter.prev()` in forward mode,
+                // `iter.next()` if the reverse-mode fallback above was taken.
+                self.skip_tombstoned(self.reverse)
             }
         }
     }
@@ -303,8 +512,17 @@ impl Iterator for AgateEngineIterator {
             return Err(engine_traits::Error::Engine("Iterator invalid".to_string()));
         }
 
-        self.iter.prev();
-        self.valid()
+        // Logical "prev" (toward smaller keys) steps with `iter.next()` when the
+        // underlying iterator is open in reverse mode, and with `iter.prev()`
+        // otherwise; `skip_tombstoned` must keep walking in that same physical
+        // direction, not whatever `self.reverse` alone would imply.
+        let physical_next = self.reverse;
+        if physical_next {
+            self.iter.next();
+        } else {
+            self.iter.prev();
+        }
+        self.skip_tombstoned(physical_next)
     }
 
     fn next(&mut self) -> Result<bool> {
@@ -312,8 +530,15 @@ impl Iterator for AgateEngineIterator {
             return Err(engine_traits::Error::Engine("Iterator invalid".to_string()));
         }
 
-        self.iter.next();
-        self.valid()
+        // See `prev`'s comment: logical "next" steps with `iter.prev()` when the
+        // underlying iterator is open in reverse mode.
+        let physical_next = !self.reverse;
+        if physical_next {
+            self.iter.next();
+        } else {
+            self.iter.prev();
+        }
+        self.skip_tombstoned(physical_next)
     }
 
     fn key(&self) -> &[u8] {
@@ -327,6 +552,16 @@ impl Iterator for AgateEngineIterator {
     }
 
     fn valid(&self) -> Result<bool> {
+        self.raw_valid()
+    }
+}
+
+impl AgateEngineIterator {
+    /// Whether the iterator's current position falls inside the requested CF and
+    /// bounds. Doesn't check tombstone coverage; `seek`/`next`/`prev` route through
+    /// `skip_tombstoned` instead, which calls this and then steps past covered keys, so
+    /// by the time callers observe `true` the position is never a tombstoned key.
+    fn raw_valid(&self) -> Result<bool> {
         if !self.iter.valid() {
             return Ok(false);
         }
@@ -344,13 +579,13 @@ impl Iterator for AgateEngineIterator {
 
         if self.opts.lower_bound().is_some() {
             let lower = self.opts.lower_bound().unwrap();
-            if !lower.is_empty() && key < lower {
+            if !lower.is_empty() && (self.comparator.cmp)(&key, lower) == std::cmp::Ordering::Less {
                 return Ok(false);
             }
         }
         if self.opts.upper_bound().is_some() {
             let upper = self.opts.upper_bound().unwrap();
-            if !upper.is_empty() && key >= upper {
+            if !upper.is_empty() && (self.comparator.cmp)(&key, upper) != std::cmp::Ordering::Less {
                 return Ok(false);
             }
         }
@@ -556,4 +791,76 @@ mod tests {
             .unwrap();
         assert_eq!(data, vec![(b"a1".to_vec(), b"v1".to_vec()),]);
     }
+
+    #[test]
+    fn test_delete_ranges_cf_tombstone() {
+        use engine_traits::Range;
+
+        let path = Builder::new().prefix("var").tempdir().unwrap();
+        let cf = "cf";
+        let engine = AgateEngine::new(path.path(), vec![cf.to_string()]);
+
+        engine.put_cf(cf, b"a1", b"v1").unwrap();
+        engine.put_cf(cf, b"a2", b"v2").unwrap();
+        engine.put_cf(cf, b"a3", b"v3").unwrap();
+
+        engine
+            .delete_ranges_cf_tombstone(cf, &[Range::new(b"a1", b"a3")], true)
+            .unwrap();
+
+        // Hidden by the tombstone immediately, even though nothing has been physically
+        // removed yet.
+        assert!(engine.get_value_cf(cf, b"a1").unwrap().is_none());
+        assert!(engine.get_value_cf(cf, b"a2").unwrap().is_none());
+        assert_eq!(
+            &*engine.get_value_cf(cf, b"a3").unwrap().unwrap(),
+            b"v3".as_slice()
+        );
+
+        let mut data = vec![];
+        engine
+            .scan_cf(cf, b"", &[0xFF, 0xFF], false, |key, value| {
+                data.push((key.to_vec(), value.to_vec()));
+                Ok(true)
+            })
+            .unwrap();
+        assert_eq!(data, vec![(b"a3".to_vec(), b"v3".to_vec())]);
+
+        // Nothing was physically removed yet; running the lazy GC pass (what
+        // compaction does) now actually reclaims the covered keys.
+        engine.gc_tombstones_cf(cf).unwrap();
+        data.clear();
+        engine
+            .scan_cf(cf, b"", &[0xFF, 0xFF], false, |key, value| {
+                data.push((key.to_vec(), value.to_vec()));
+                Ok(true)
+            })
+            .unwrap();
+        assert_eq!(data, vec![(b"a3".to_vec(), b"v3".to_vec())]);
+    }
+
+    #[test]
+    fn test_txn_config() {
+        use engine_traits::MiscExt;
+
+        use crate::txn_config::{TxnConfig, TxnMode};
+
+        let path = Builder::new().prefix("var").tempdir().unwrap();
+
+        let mut txn_config = TxnConfig::default();
+        txn_config
+            .set_mode(TxnMode::Pessimistic)
+            .set_deadlock_detect(true)
+            .allow_write_stall(false);
+
+        let engine =
+            AgateEngine::new_with_txn_config(path.path(), vec![], Default::default(), txn_config);
+
+        engine.put(b"k1", b"v1").unwrap();
+        assert_eq!(&*engine.get_value(b"k1").unwrap().unwrap(), b"v1");
+
+        // `sync`/`sync_wal` return immediately whatever `allow_write_stall` is set to.
+        engine.sync().unwrap();
+        engine.sync_wal().unwrap();
+    }
 }
@@ -5,15 +5,65 @@ use engine_traits::{FlowControlFactorsExt, Result};
 use crate::engine::AgateEngine;
 
 impl FlowControlFactorsExt for AgateEngine {
-    fn get_cf_num_files_at_level(&self, cf: &str, level: usize) -> Result<Option<u64>> {
-        panic!()
+    fn get_cf_num_files_at_level(&self, cf: &str, _level: usize) -> Result<Option<u64>> {
+        self.check_cf_exist(cf)?;
+        // TODO: AgateDB doesn't expose per-level SST enumeration to this engine (the
+        // same gap `TablePropertiesExt::table_properties_collection` works around with
+        // a live scan instead of real SST footers), so there's no real per-level file
+        // count to report here.
+        Ok(None)
     }
 
     fn get_cf_num_immutable_mem_table(&self, cf: &str) -> Result<Option<u64>> {
-        panic!()
+        self.check_cf_exist(cf)?;
+        // TODO: ditto -- AgateDB's sealed-but-unflushed memtable count isn't surfaced
+        // to this engine.
+        Ok(None)
     }
 
     fn get_cf_pending_compaction_bytes(&self, cf: &str) -> Result<Option<u64>> {
-        panic!()
+        self.check_cf_exist(cf)?;
+
+        // The one compaction-eligible quantity this engine can report honestly: the
+        // size of the keys still physically present under `cf`'s outstanding range
+        // tombstones (see `tombstone::TombstoneList`), which `gc_tombstones_cf` reclaims
+        // the next time this CF is compacted. This undercounts real write-amplification
+        // debt, since AgateDB doesn't expose per-level SST sizes queued for compaction,
+        // but it's a real, nonzero number rather than a fabricated one.
+        let mut pending_bytes = 0u64;
+
+        for tombstone in self.tombstones.peek(cf) {
+            pending_bytes += self.raw_bytes_in_range(cf, &tombstone.begin_key, &tombstone.end_key);
+        }
+
+        Ok(Some(pending_bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{Range, SyncMutable};
+    use tempfile::Builder;
+
+    use super::*;
+
+    #[test]
+    fn test_flow_control_factors() {
+        let path = Builder::new().prefix("var").tempdir().unwrap();
+        let cf = "cf";
+        let engine = AgateEngine::new(path.path(), vec![cf.to_string()]);
+
+        assert_eq!(engine.get_cf_num_files_at_level(cf, 0).unwrap(), None);
+        assert_eq!(engine.get_cf_num_immutable_mem_table(cf).unwrap(), None);
+        assert_eq!(engine.get_cf_pending_compaction_bytes(cf).unwrap(), Some(0));
+
+        engine.put_cf(cf, b"a1", b"v1").unwrap();
+        engine.put_cf(cf, b"a2", b"v2").unwrap();
+        engine
+            .delete_ranges_cf_tombstone(cf, &[Range::new(b"a1", b"a3")], true)
+            .unwrap();
+
+        let pending = engine.get_cf_pending_compaction_bytes(cf).unwrap().unwrap();
+        assert!(pending > 0);
     }
 }
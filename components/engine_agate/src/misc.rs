@@ -1,12 +1,17 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::{fs, path::Path};
+
 use engine_traits::{
-    DeleteStrategy, IterOptions, Iterable, Iterator, MiscExt, Mutable, Range, Result, WriteBatch,
-    WriteBatchExt,
+    DeleteStrategy, ImportExt, IterOptions, Iterable, Iterator, MiscExt, Mutable, Range, Result,
+    SstExt, SstWriter, SstWriterBuilder, WriteBatch, WriteBatchExt,
 };
 use tikv_util::keybuilder::KeyBuilder;
 
-use crate::engine::AgateEngine;
+use crate::{
+    engine::AgateEngine,
+    txn_config::{TxnConfig, TxnMode},
+};
 
 impl MiscExt for AgateEngine {
     fn flush(&self, sync: bool) -> Result<()> {
@@ -29,28 +34,14 @@ impl MiscExt for AgateEngine {
             return Ok(());
         }
 
-        for range in ranges {
-            let start = KeyBuilder::from_slice(range.start_key, 0, 0);
-            let end = KeyBuilder::from_slice(range.end_key, 0, 0);
-            let mut opts = IterOptions::new(Some(start), Some(end), false);
-            let mut it = self.iterator_cf_opt(cf, opts)?;
-            let mut it_valid = it.seek(range.start_key.into())?;
-            let mut wb = self.write_batch();
-            while it_valid {
-                wb.delete_cf(cf, it.key())?;
-                if wb.count() >= Self::WRITE_BATCH_MAX_KEYS {
-                    wb.write()?;
-                    wb.clear();
-                }
-                it_valid = it.next()?;
+        match strategy {
+            DeleteStrategy::DeleteByKey => self.delete_ranges_cf_by_key(cf, ranges),
+            DeleteStrategy::DeleteByRange => self.delete_ranges_cf_by_range(cf, ranges),
+            DeleteStrategy::DeleteByWriter { sst_path } => {
+                self.delete_ranges_cf_by_writer(cf, ranges, sst_path)
             }
-            if wb.count() > 0 {
-                wb.write()?;
-            }
-            self.sync_wal()?;
+            DeleteStrategy::DeleteFiles => self.delete_ranges_cf_by_files(cf, ranges),
         }
-
-        Ok(())
     }
 
     fn get_approximate_memtable_stats_cf(&self, cf: &str, range: &Range<'_>) -> Result<(u64, u64)> {
@@ -79,7 +70,16 @@ impl MiscExt for AgateEngine {
     }
 
     fn sync_wal(&self) -> Result<()> {
-        // TODO: Implement this for AgateDB.
+        if !self.txn_config.allow_write_stall {
+            // Bulk-ingest workloads opted out of write stalls via `TxnConfig`: skip
+            // waiting on a durability hook entirely rather than risk blocking the
+            // caller on it.
+            return Ok(());
+        }
+
+        // TODO: AgateDB doesn't expose a WAL/value-log sync hook to this engine yet;
+        // once it does, call it here so `allow_write_stall` callers actually wait for
+        // it instead of this being a no-op either way.
         Ok(())
     }
 
@@ -123,3 +123,110 @@ impl MiscExt for AgateEngine {
         false
     }
 }
+
+impl AgateEngine {
+    /// Deletes every key in `ranges` one at a time. This is the only strategy that can
+    /// remove keys falling into a sub-range without touching the rest, so it's the
+    /// right choice for small ranges, but it's O(n) in the number of keys touched.
+    fn delete_ranges_cf_by_key(&self, cf: &str, ranges: &[Range<'_>]) -> Result<()> {
+        for range in ranges {
+            let start = KeyBuilder::from_slice(range.start_key, 0, 0);
+            let end = KeyBuilder::from_slice(range.end_key, 0, 0);
+            let opts = IterOptions::new(Some(start), Some(end), false);
+            let mut it = self.iterator_cf_opt(cf, opts)?;
+            let mut it_valid = it.seek(range.start_key.into())?;
+            // "Delete whatever's in this range right now" has no read set to defend;
+            // `TxnMode::Pessimistic` skips `AgateWriteBatch`'s optimistic conflict check,
+            // so a concurrent writer touching the range doesn't turn this bulk delete
+            // into a spurious `Error::Conflict`.
+            let mut wb = self.write_batch_opt(TxnConfig {
+                mode: TxnMode::Pessimistic,
+                ..self.txn_config
+            });
+            while it_valid {
+                wb.delete_cf(cf, it.key())?;
+                if wb.count() >= Self::WRITE_BATCH_MAX_KEYS {
+                    wb.write()?;
+                    wb.clear();
+                }
+                it_valid = it.next()?;
+            }
+            if wb.count() > 0 {
+                wb.write()?;
+            }
+            self.sync_wal()?;
+        }
+
+        Ok(())
+    }
+
+    /// Stages each range as one `DeleteRange` op in a single write batch. When the batch
+    /// commits it still scans every key the range covers and deletes them one by one,
+    /// all in one transaction rather than in `WRITE_BATCH_MAX_KEYS`-sized chunks as
+    /// `DeleteByKey` does; no native range deletion is issued.
+    ///
+    /// `pub(crate)` so `tombstone::gc_tombstones_cf` can reuse it as the "actually
+    /// remove these keys now" step once a tombstone's lazy GC runs.
+    pub(crate) fn delete_ranges_cf_by_range(&self, cf: &str, ranges: &[Range<'_>]) -> Result<()> {
+        // See the comment in `delete_ranges_cf_by_key`: this path (also reused by
+        // tombstone GC) wants every matching key gone, not a conflict error if one
+        // shows up mid-delete.
+        let mut wb = self.write_batch_opt(TxnConfig {
+            mode: TxnMode::Pessimistic,
+            ..self.txn_config
+        });
+        for range in ranges {
+            wb.delete_range_cf(cf, range.start_key, range.end_key)?;
+        }
+        wb.write()?;
+        self.sync_wal()
+    }
+
+    /// Builds a tombstone SST covering every key currently in `ranges` and ingests it
+    /// through `ingest_external_file_cf`, which replays its delete entries through write
+    /// batches. Returns without writing anything if the ranges hold no keys.
+    fn delete_ranges_cf_by_writer(&self, cf: &str, ranges: &[Range<'_>], sst_path: String) -> Result<()> {
+        use crate::sst::{AgateSstWriter, AgateSstWriterBuilder};
+
+        let mut writer = AgateSstWriterBuilder::new()
+            .set_db(self)
+            .set_cf(cf)
+            .build(&sst_path)?;
+
+        let mut any_key = false;
+        for range in ranges {
+            let start = KeyBuilder::from_slice(range.start_key, 0, 0);
+            let end = KeyBuilder::from_slice(range.end_key, 0, 0);
+            let opts = IterOptions::new(Some(start), Some(end), false);
+            let mut it = self.iterator_cf_opt(cf, opts)?;
+            let mut it_valid = it.seek(range.start_key.into())?;
+            while it_valid {
+                writer.delete(it.key())?;
+                any_key = true;
+                it_valid = it.next()?;
+            }
+        }
+
+        if !any_key {
+            return Ok(());
+        }
+
+        writer.finish()?;
+        self.ingest_external_file_cf(cf, &[sst_path.as_str()])?;
+
+        // `sst_path` is a scratch file this method built and owns, not one an external
+        // caller handed in to keep around, so it (and its bloom sidecar) are always
+        // cleaned up here -- regardless of `ingest_external_file_cf`'s own
+        // `move_files` default, which now honors callers that want their own SSTs kept.
+        let _ = fs::remove_file(&sst_path);
+        let _ = fs::remove_file(crate::bloom::filter_path(Path::new(&sst_path)));
+
+        Ok(())
+    }
+
+    /// Handles every range with `DeleteByRange`. AgateDB exposes no per-file key-range
+    /// metadata to this engine, so no SST file is ever dropped whole.
+    fn delete_ranges_cf_by_files(&self, cf: &str, ranges: &[Range<'_>]) -> Result<()> {
+        self.delete_ranges_cf_by_range(cf, ranges)
+    }
+}
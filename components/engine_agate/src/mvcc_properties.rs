@@ -1,7 +1,7 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
-use engine_traits::{MvccProperties, MvccPropertiesExt, Result};
-use txn_types::TimeStamp;
+use engine_traits::{Iterable, MvccProperties, MvccPropertiesExt, Result};
+use txn_types::{Key, TimeStamp, Write, WriteType};
 
 use crate::engine::AgateEngine;
 
@@ -13,6 +13,61 @@ impl MvccPropertiesExt for AgateEngine {
         start_key: &[u8],
         end_key: &[u8],
     ) -> Option<MvccProperties> {
-        panic!()
+        self.check_cf_exist(cf).ok()?;
+
+        let mut props = MvccProperties::new();
+        let mut any_row = false;
+
+        // Tracks the user key currently being walked, and whether we've already passed
+        // its first version (newest-first, since writes are ordered by descending
+        // commit_ts) at or below `safe_point`. A real GC run would reclaim every older
+        // version once that one is seen, so counting stops there too.
+        let mut cur_user_key: Vec<u8> = Vec::new();
+        let mut cur_key_done = false;
+
+        self.scan_cf(cf, start_key, end_key, false, |key, value| {
+            let (user_key, commit_ts) = match Key::split_on_ts_for(key) {
+                Ok((user_key, commit_ts)) => (user_key, commit_ts),
+                Err(_) => return Ok(true),
+            };
+
+            if user_key != cur_user_key.as_slice() {
+                cur_user_key = user_key.to_vec();
+                cur_key_done = false;
+                props.num_rows += 1;
+                any_row = true;
+            }
+
+            if cur_key_done {
+                // This key's first version at-or-below `safe_point` was already seen;
+                // everything older than it is GC-eligible and excluded from the counts.
+                return Ok(true);
+            }
+
+            if commit_ts <= safe_point {
+                cur_key_done = true;
+            }
+
+            props.min_ts = std::cmp::min(props.min_ts, commit_ts);
+            props.max_ts = std::cmp::max(props.max_ts, commit_ts);
+            props.num_versions += 1;
+
+            if let Ok(write) = Write::parse(value) {
+                match write.write_type {
+                    WriteType::Put => props.num_puts += 1,
+                    WriteType::Delete => props.num_deletes += 1,
+                    WriteType::Lock | WriteType::Rollback => {}
+                }
+            }
+
+            Ok(true)
+        })
+        .ok()?;
+
+        if !any_row {
+            return None;
+        }
+
+        Some(props)
     }
-}
\ No newline at end of file
+}
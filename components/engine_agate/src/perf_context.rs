@@ -1,7 +1,9 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::time::Instant;
+
 use engine_traits::{PerfContext, PerfContextExt, PerfContextKind, PerfLevel};
-use tracker::TrackerToken;
+use tracker::{TrackerToken, GLOBAL_TRACKERS};
 
 use crate::engine::AgateEngine;
 
@@ -9,18 +11,49 @@ impl PerfContextExt for AgateEngine {
     type PerfContext = AgatePerfContext;
 
     fn get_perf_context(&self, level: PerfLevel, kind: PerfContextKind) -> Self::PerfContext {
-        panic!()
+        AgatePerfContext {
+            kind,
+            level,
+            start: None,
+        }
     }
 }
 
-pub struct AgatePerfContext;
+pub struct AgatePerfContext {
+    // Which request path this context instruments (e.g. raftstore apply vs.
+    // coprocessor get) and at what granularity. Neither drives any behavior today --
+    // see `report_metrics` -- but both are kept so a real per-operation-counter
+    // implementation can scope itself by them later without another signature change.
+    #[allow(dead_code)]
+    kind: PerfContextKind,
+    #[allow(dead_code)]
+    level: PerfLevel,
+    start: Option<Instant>,
+}
 
 impl PerfContext for AgatePerfContext {
     fn start_observe(&mut self) {
-        panic!()
+        self.start = Some(Instant::now());
     }
 
-    fn report_metrics(&mut self, _: &[TrackerToken]) {
-        panic!()
+    /// Publishes wall-time elapsed since `start_observe` to `trackers`.
+    ///
+    /// TODO: AgateDB doesn't expose per-operation block-read/seek/next counters to
+    /// this engine -- no `get`/`AgateEngineIterator` call threads a counter handle
+    /// back to this context -- so unlike RocksDB's `PerfContext` this never updates
+    /// `rocksdb_block_read_count`, `rocksdb_block_read_byte`, or
+    /// `rocksdb_key_skipped_count`. Only wall time is real here.
+    fn report_metrics(&mut self, trackers: &[TrackerToken]) {
+        let elapsed_ns = self
+            .start
+            .take()
+            .map(|start| start.elapsed().as_nanos() as u64)
+            .unwrap_or(0);
+
+        for tracker_token in trackers {
+            GLOBAL_TRACKERS.with_tracker(*tracker_token, |tracker| {
+                tracker.metrics.internal_delay_ns += elapsed_ns;
+            });
+        }
     }
 }
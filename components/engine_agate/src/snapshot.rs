@@ -9,13 +9,19 @@ use engine_traits::{
 };
 
 use crate::{
-    db_vector::AgateDBVector, engine::AgateEngine, utils::add_cf_prefix, AgateEngineIterator,
+    db_vector::AgateDBVector,
+    engine::AgateEngine,
+    tombstone::TombstoneList,
+    utils::{add_cf_prefix, KeyComparator},
+    AgateEngineIterator,
 };
 
 #[derive(Clone)]
 pub struct AgateSnapshot {
     txn: agatedb::Transaction,
     cf_names: HashSet<String>,
+    comparator: KeyComparator,
+    tombstones: TombstoneList,
 }
 
 impl Debug for AgateSnapshot {
@@ -32,7 +38,12 @@ impl AgateSnapshot {
         let txn = engine.agate.new_transaction(false);
         let cf_names = engine.cf_names().iter().map(|x| x.to_string()).collect();
 
-        AgateSnapshot { txn, cf_names }
+        AgateSnapshot {
+            txn,
+            cf_names,
+            comparator: engine.comparator.clone(),
+            tombstones: engine.tombstones.clone(),
+        }
     }
 
     pub fn check_cf_exist(&self, cf: &str) -> Result<()> {
@@ -55,6 +66,10 @@ impl Peekable for AgateSnapshot {
     type DBVector = AgateDBVector;
 
     fn get_value_opt(&self, opts: &ReadOptions, key: &[u8]) -> Result<Option<Self::DBVector>> {
+        if self.tombstones.is_covered(engine_traits::CF_DEFAULT, key) {
+            return Ok(None);
+        }
+
         let key = &add_cf_prefix(key, None);
 
         match self.txn.get(&Bytes::copy_from_slice(key)) {
@@ -73,6 +88,10 @@ impl Peekable for AgateSnapshot {
     ) -> Result<Option<Self::DBVector>> {
         self.check_cf_exist(cf)?;
 
+        if self.tombstones.is_covered(cf, key) {
+            return Ok(None);
+        }
+
         let key = &add_cf_prefix(key, Some(cf.to_string()));
 
         match self.txn.get(&Bytes::copy_from_slice(key)) {
@@ -92,9 +111,13 @@ impl Iterable for AgateSnapshot {
         let iter = self.txn.new_iterator(&IteratorOptions::default());
 
         Ok(AgateEngineIterator {
+            txn: self.txn.clone(),
             iter,
+            reverse: false,
             opts,
             cf_name: None,
+            comparator: self.comparator.clone(),
+            tombstones: self.tombstones.clone(),
         })
     }
     fn iterator_cf_opt(&self, cf: &str, opts: IterOptions) -> Result<Self::Iterator> {
@@ -103,9 +126,13 @@ impl Iterable for AgateSnapshot {
         let iter = self.txn.new_iterator(&IteratorOptions::default());
 
         Ok(AgateEngineIterator {
+            txn: self.txn.clone(),
             iter,
+            reverse: false,
             opts,
             cf_name: Some(cf.to_owned()),
+            comparator: self.comparator.clone(),
+            tombstones: self.tombstones.clone(),
         })
     }
 }
@@ -16,12 +16,34 @@ use engine_traits::{
 };
 
 use crate::{
+    bloom::{filter_path, BloomFilter},
     engine::AgateEngine,
     utils::{add_cf_prefix, get_cf_and_key},
 };
 
 const SST_USER_META: u8 = 1 << 2;
 
+/// Default bits-per-key a `AgateSstWriterBuilder` sizes its filter with when the caller
+/// doesn't override it: the classic LevelDB/RocksDB rule-of-thumb value, good for about
+/// a 1% false-positive rate.
+const DEFAULT_BLOOM_BITS_PER_KEY: u32 = 10;
+
+/// Maps a TiKV `SstCompressionType` onto the block compressor `TableBuilder` should use,
+/// defaulting to no compression when the caller didn't ask for one.
+///
+/// TODO: AgateDB's block compressor doesn't have a dedicated LZ4 codec; Snappy is the
+/// closest fast, low-ratio option it does support, so `Lz4` maps to it for now.
+fn agate_compression_type(compression_type: Option<SstCompressionType>) -> agatedb::opt::CompressionType {
+    use agatedb::opt::CompressionType;
+
+    match compression_type {
+        None => CompressionType::None,
+        Some(SstCompressionType::Zstd) => CompressionType::ZSTD,
+        Some(SstCompressionType::Lz4) => CompressionType::Snappy,
+        Some(SstCompressionType::Snappy) => CompressionType::Snappy,
+    }
+}
+
 impl SstExt for AgateEngine {
     type SstReader = AgateSstReader;
     type SstWriter = AgateSstWriter;
@@ -30,6 +52,7 @@ impl SstExt for AgateEngine {
 
 pub struct AgateSstReader {
     table: Table,
+    filter: BloomFilter,
 }
 
 impl SstReader for AgateSstReader {
@@ -38,8 +61,9 @@ impl SstReader for AgateSstReader {
         let opts = build_table_options(&AgateOptions::default());
         let table =
             Table::open(path, opts).map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
+        let filter = BloomFilter::load(&filter_path(path));
 
-        Ok(Self { table })
+        Ok(Self { table, filter })
     }
     fn verify_checksum(&self) -> Result<()> {
         self.table
@@ -53,6 +77,42 @@ impl SstReader for AgateSstReader {
     }
 }
 
+impl AgateSstReader {
+    /// Fast-path existence probe for `key` in the default CF: hashes the CF-prefixed
+    /// key the same way `AgateSstWriter` did when it built the filter, and reports
+    /// `false` only when the table is guaranteed not to hold `key`. A `true` result
+    /// still requires an actual seek to confirm.
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.may_contain_cf(CF_DEFAULT, key)
+    }
+
+    /// Like `may_contain`, but probes `key` within `cf`.
+    pub fn may_contain_cf(&self, cf: &str, key: &[u8]) -> bool {
+        let key = add_cf_prefix(key, Some(cf.to_string()));
+        self.filter.may_contain(&key)
+    }
+
+    /// Point lookup of `key` in the default CF, short-circuiting to `Ok(None)` on a
+    /// negative `may_contain` instead of paying for an index seek.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.get_cf(CF_DEFAULT, key)
+    }
+
+    /// Like `get`, but looks `key` up within `cf`.
+    pub fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if !self.may_contain_cf(cf, key) {
+            return Ok(None);
+        }
+
+        let mut it = self.iterator_cf_opt(cf, IterOptions::default())?;
+        if it.seek(SeekKey::Key(key))? && it.key() == key {
+            Ok(Some(it.value().to_vec()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
 impl Iterable for AgateSstReader {
     type Iterator = AgateSstReaderIterator;
 
@@ -225,6 +285,11 @@ pub struct AgateSstWriter {
     cf_name: Option<String>,
     path: PathBuf,
     last_key: Bytes,
+    // CF-prefixed keys seen so far, in the same encoding stored in the table, so the
+    // filter built over them at `finish` matches what `AgateSstReader::may_contain`
+    // probes with.
+    keys: Vec<Vec<u8>>,
+    bits_per_key: u32,
 }
 
 impl SstWriter for AgateSstWriter {
@@ -237,6 +302,7 @@ impl SstWriter for AgateSstWriter {
             return Err(engine_traits::Error::Engine("Key not in order".to_string()));
         }
         self.last_key = key.clone();
+        self.keys.push(key.to_vec());
 
         let value = Value::new_with_meta(Bytes::copy_from_slice(val), 0, SST_USER_META);
 
@@ -249,6 +315,7 @@ impl SstWriter for AgateSstWriter {
             return Err(engine_traits::Error::Engine("Key not in order".to_string()));
         }
         self.last_key = key.clone();
+        self.keys.push(key.to_vec());
 
         let value = Value::new_with_meta(Bytes::new(), VALUE_DELETE, SST_USER_META);
 
@@ -259,6 +326,11 @@ impl SstWriter for AgateSstWriter {
         self.builder.estimated_size() as u64
     }
     fn finish(self) -> Result<Self::ExternalSstFileInfo> {
+        let filter = BloomFilter::build(&self.keys, self.bits_per_key);
+        filter
+            .save(&filter_path(&self.path))
+            .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
+
         let table =
             Table::create(&self.path, self.builder.finish(), TableOptions::default()).unwrap();
         table.mark_save();
@@ -279,6 +351,17 @@ pub struct AgateSstWriterBuilder {
     in_memory: bool,
     compression_type: Option<SstCompressionType>,
     compression_level: i32,
+    bits_per_key: u32,
+}
+
+impl AgateSstWriterBuilder {
+    /// Sizes the filter `AgateSstWriter::finish` builds at `bits_per_key` bits per
+    /// entry; higher values trade filter size for a lower false-positive rate.
+    /// Defaults to `DEFAULT_BLOOM_BITS_PER_KEY` when unset.
+    pub fn set_bits_per_key(mut self, bits_per_key: u32) -> Self {
+        self.bits_per_key = bits_per_key;
+        self
+    }
 }
 
 impl SstWriterBuilder<AgateEngine> for AgateSstWriterBuilder {
@@ -289,6 +372,7 @@ impl SstWriterBuilder<AgateEngine> for AgateSstWriterBuilder {
             in_memory: false,
             compression_type: None,
             compression_level: 0,
+            bits_per_key: DEFAULT_BLOOM_BITS_PER_KEY,
         }
     }
     fn set_db(mut self, db: &AgateEngine) -> Self {
@@ -313,12 +397,19 @@ impl SstWriterBuilder<AgateEngine> for AgateSstWriterBuilder {
     }
 
     fn build(self, path: &str) -> Result<AgateSstWriter> {
-        let builder = TableBuilder::new(TableOptions::default());
+        let table_opts = TableOptions {
+            compression: agate_compression_type(self.compression_type),
+            zstd_compression_level: self.compression_level,
+            ..Default::default()
+        };
+        let builder = TableBuilder::new(table_opts);
         Ok(AgateSstWriter {
             builder,
             cf_name: self.cf_name,
             path: PathBuf::from(path),
             last_key: Bytes::new(),
+            keys: Vec::new(),
+            bits_per_key: self.bits_per_key,
         })
     }
 }
@@ -361,3 +452,148 @@ impl std::io::Read for AgateExternalSstFileReader {
         panic!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::Builder;
+
+    use super::*;
+
+    fn write_and_read_back(compression_type: Option<SstCompressionType>) {
+        let dir = Builder::new().prefix("var").tempdir().unwrap();
+        let sst_path = dir.path().join("test.sst");
+        let cf = "cf";
+
+        let mut writer = AgateSstWriterBuilder::new()
+            .set_cf(cf)
+            .set_compression_type(compression_type)
+            .set_compression_level(3)
+            .build(sst_path.to_str().unwrap())
+            .unwrap();
+        writer.put(b"k1", b"v1").unwrap();
+        writer.put(b"k2", b"v2").unwrap();
+        writer.put(b"k3", b"v3").unwrap();
+        writer.finish().unwrap();
+
+        let reader = AgateSstReader::open(sst_path.to_str().unwrap()).unwrap();
+        reader.verify_checksum().unwrap();
+
+        let mut it = reader.iterator_cf_opt(cf, IterOptions::default()).unwrap();
+        let mut data = vec![];
+        let mut it_valid = it.seek(SeekKey::Start).unwrap();
+        while it_valid {
+            data.push((it.key().to_vec(), it.value().to_vec()));
+            it_valid = it.next().unwrap();
+        }
+
+        assert_eq!(
+            data,
+            vec![
+                (b"k1".to_vec(), b"v1".to_vec()),
+                (b"k2".to_vec(), b"v2".to_vec()),
+                (b"k3".to_vec(), b"v3".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_back_uncompressed() {
+        write_and_read_back(None);
+    }
+
+    #[test]
+    fn test_write_and_read_back_zstd() {
+        write_and_read_back(Some(SstCompressionType::Zstd));
+    }
+
+    #[test]
+    fn test_write_and_read_back_snappy() {
+        write_and_read_back(Some(SstCompressionType::Snappy));
+    }
+
+    #[test]
+    fn test_write_and_read_back_lz4() {
+        write_and_read_back(Some(SstCompressionType::Lz4));
+    }
+
+    #[test]
+    fn test_get_and_may_contain_no_false_negatives() {
+        let dir = Builder::new().prefix("var").tempdir().unwrap();
+        let sst_path = dir.path().join("test.sst");
+        let cf = "cf";
+
+        let present: Vec<Vec<u8>> = (0..200u32).map(|i| i.to_be_bytes().to_vec()).collect();
+
+        let mut writer = AgateSstWriterBuilder::new()
+            .set_cf(cf)
+            .set_bits_per_key(10)
+            .build(sst_path.to_str().unwrap())
+            .unwrap();
+        for key in &present {
+            writer.put(key, key).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = AgateSstReader::open(sst_path.to_str().unwrap()).unwrap();
+        for key in &present {
+            assert!(reader.may_contain_cf(cf, key));
+            assert_eq!(reader.get_cf(cf, key).unwrap(), Some(key.clone()));
+        }
+    }
+
+    #[test]
+    fn test_may_contain_false_positive_rate_is_bounded() {
+        let dir = Builder::new().prefix("var").tempdir().unwrap();
+        let sst_path = dir.path().join("test.sst");
+        let cf = "cf";
+
+        let present: Vec<Vec<u8>> = (0..1000u32).map(|i| i.to_be_bytes().to_vec()).collect();
+
+        let mut writer = AgateSstWriterBuilder::new()
+            .set_cf(cf)
+            .set_bits_per_key(10)
+            .build(sst_path.to_str().unwrap())
+            .unwrap();
+        for key in &present {
+            writer.put(key, key).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = AgateSstReader::open(sst_path.to_str().unwrap()).unwrap();
+
+        let num_absent = 10_000u32;
+        let mut false_positives = 0;
+        for i in 1_000_000..1_000_000 + num_absent {
+            if reader.may_contain_cf(cf, &i.to_be_bytes()) {
+                false_positives += 1;
+            }
+        }
+
+        // Mirrors the headroom in `bloom::tests`: 10 bits/key keeps the rate near 1%,
+        // leave plenty of slack so the test isn't flaky.
+        assert!(
+            (false_positives as f64) < (num_absent as f64) * 0.05,
+            "false positive rate too high: {false_positives}/{num_absent}"
+        );
+    }
+
+    #[test]
+    fn test_get_missing_sidecar_falls_back_to_no_filtering() {
+        let dir = Builder::new().prefix("var").tempdir().unwrap();
+        let sst_path = dir.path().join("test.sst");
+        let cf = "cf";
+
+        let mut writer = AgateSstWriterBuilder::new()
+            .set_cf(cf)
+            .build(sst_path.to_str().unwrap())
+            .unwrap();
+        writer.put(b"k1", b"v1").unwrap();
+        writer.finish().unwrap();
+
+        std::fs::remove_file(filter_path(&sst_path)).unwrap();
+
+        let reader = AgateSstReader::open(sst_path.to_str().unwrap()).unwrap();
+        assert!(reader.may_contain_cf(cf, b"nonexistent"));
+        assert_eq!(reader.get_cf(cf, b"k1").unwrap(), Some(b"v1".to_vec()));
+    }
+}
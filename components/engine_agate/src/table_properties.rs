@@ -1,36 +1,199 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
-use engine_traits::{Range, Result};
+use engine_traits::{Iterable, Range, Result};
+use fst::{Map as FstMap, MapBuilder};
+use txn_types::{Key, TimeStamp, Write, WriteType};
 
 use crate::engine::AgateEngine;
 
-pub struct UserCollectedProperties;
+/// One (synthetic) table's worth of user-collected properties. Property values are
+/// addressed through an `fst`-backed map from property name to an index into `values`,
+/// so `get` is a prefix-automaton walk over the encoded key set rather than a linear
+/// scan of the serialized blob.
+pub struct UserCollectedProperties {
+    index: FstMap<Vec<u8>>,
+    values: Vec<Vec<u8>>,
+    smallest_key: Vec<u8>,
+    largest_key: Vec<u8>,
+    total_size: usize,
+    total_keys: usize,
+}
+
+struct TableSummary {
+    smallest_key: Vec<u8>,
+    largest_key: Vec<u8>,
+    total_size: usize,
+    total_keys: usize,
+    min_ts: TimeStamp,
+    max_ts: TimeStamp,
+    num_versions: u64,
+    num_puts: u64,
+    num_deletes: u64,
+    num_rows: u64,
+}
+
+impl UserCollectedProperties {
+    fn build(summary: TableSummary) -> Self {
+        let mut entries: Vec<(&'static str, Vec<u8>)> = vec![
+            ("max_ts", summary.max_ts.into_inner().to_be_bytes().to_vec()),
+            ("min_ts", summary.min_ts.into_inner().to_be_bytes().to_vec()),
+            ("num_deletes", summary.num_deletes.to_be_bytes().to_vec()),
+            ("num_puts", summary.num_puts.to_be_bytes().to_vec()),
+            ("num_rows", summary.num_rows.to_be_bytes().to_vec()),
+            ("num_versions", summary.num_versions.to_be_bytes().to_vec()),
+            ("total_keys", (summary.total_keys as u64).to_be_bytes().to_vec()),
+            ("total_size", (summary.total_size as u64).to_be_bytes().to_vec()),
+        ];
+        // fst requires keys to be inserted in sorted order.
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut builder = MapBuilder::memory();
+        let mut values = Vec::with_capacity(entries.len());
+        for (i, (name, value)) in entries.into_iter().enumerate() {
+            builder.insert(name.as_bytes(), i as u64).unwrap();
+            values.push(value);
+        }
+        let index = FstMap::new(builder.into_inner().unwrap()).unwrap();
+
+        UserCollectedProperties {
+            index,
+            values,
+            smallest_key: summary.smallest_key,
+            largest_key: summary.largest_key,
+            total_size: summary.total_size,
+            total_keys: summary.total_keys,
+        }
+    }
+}
+
 impl engine_traits::UserCollectedProperties for UserCollectedProperties {
-    fn get(&self, _: &[u8]) -> Option<&[u8]> {
-        None
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        let idx = self.index.get(key)?;
+        self.values.get(idx as usize).map(|v| v.as_slice())
     }
-    fn approximate_size_and_keys(&self, _: &[u8], _: &[u8]) -> Option<(usize, usize)> {
-        None
+
+    fn approximate_size_and_keys(&self, start: &[u8], end: &[u8]) -> Option<(usize, usize)> {
+        if self.total_keys == 0 || self.largest_key < self.smallest_key {
+            return None;
+        }
+        if end <= self.smallest_key.as_slice() || start > self.largest_key.as_slice() {
+            return Some((0, 0));
+        }
+
+        // Treat each key's leading bytes as a fixed-point fraction and interpolate
+        // linearly, assuming keys and bytes are spread roughly evenly across the
+        // table's key space.
+        fn as_fraction(key: &[u8]) -> f64 {
+            let mut v = 0f64;
+            for (i, &b) in key.iter().take(8).enumerate() {
+                v += b as f64 / 256f64.powi(i as i32 + 1);
+            }
+            v
+        }
+
+        let span = (as_fraction(&self.largest_key) - as_fraction(&self.smallest_key)).max(1e-9);
+        let lo = as_fraction(start.max(self.smallest_key.as_slice()));
+        let hi = as_fraction(end.min(self.largest_key.as_slice()));
+        let fraction = ((hi - lo).max(0.0) / span).clamp(0.0, 1.0);
+
+        Some((
+            (self.total_size as f64 * fraction) as usize,
+            (self.total_keys as f64 * fraction) as usize,
+        ))
     }
 }
 
-pub struct TablePropertiesCollection;
+pub struct TablePropertiesCollection {
+    tables: Vec<UserCollectedProperties>,
+}
+
 impl engine_traits::TablePropertiesCollection for TablePropertiesCollection {
     type UserCollectedProperties = UserCollectedProperties;
-    fn iter_user_collected_properties<F>(&self, _: F)
+
+    fn iter_user_collected_properties<F>(&self, mut f: F)
     where
         F: FnMut(&Self::UserCollectedProperties) -> bool,
     {
+        for table in &self.tables {
+            if !f(table) {
+                break;
+            }
+        }
     }
 }
 
 impl engine_traits::TablePropertiesExt for AgateEngine {
     type TablePropertiesCollection = TablePropertiesCollection;
+
     fn table_properties_collection(
         &self,
         cf: &str,
         ranges: &[Range<'_>],
     ) -> Result<Self::TablePropertiesCollection> {
-        panic!()
+        self.check_cf_exist(cf)?;
+
+        // TODO: AgateDB doesn't expose per-SST-file enumeration to this engine yet, so
+        // each requested range is summarized as a single synthetic "table" built from a
+        // live scan rather than from on-disk SST footers.
+        let mut tables = Vec::with_capacity(ranges.len());
+
+        for range in ranges {
+            let mut smallest_key: Option<Vec<u8>> = None;
+            let mut largest_key: Option<Vec<u8>> = None;
+            let mut total_size = 0usize;
+            let mut total_keys = 0usize;
+            let mut min_ts = TimeStamp::max();
+            let mut max_ts = TimeStamp::zero();
+            let mut num_versions = 0u64;
+            let mut num_puts = 0u64;
+            let mut num_deletes = 0u64;
+            let mut num_rows = 0u64;
+            let mut last_user_key: Option<Vec<u8>> = None;
+
+            self.scan_cf(cf, range.start_key, range.end_key, false, |key, value| {
+                if smallest_key.is_none() {
+                    smallest_key = Some(key.to_vec());
+                }
+                largest_key = Some(key.to_vec());
+                total_size += key.len() + value.len();
+                total_keys += 1;
+
+                if let Ok((user_key, commit_ts)) = Key::split_on_ts_for(key) {
+                    if last_user_key.as_deref() != Some(user_key) {
+                        last_user_key = Some(user_key.to_vec());
+                        num_rows += 1;
+                    }
+                    min_ts = std::cmp::min(min_ts, commit_ts);
+                    max_ts = std::cmp::max(max_ts, commit_ts);
+                    num_versions += 1;
+                    if let Ok(write) = Write::parse(value) {
+                        match write.write_type {
+                            WriteType::Put => num_puts += 1,
+                            WriteType::Delete => num_deletes += 1,
+                            WriteType::Lock | WriteType::Rollback => {}
+                        }
+                    }
+                }
+
+                Ok(true)
+            })?;
+
+            if let (Some(smallest_key), Some(largest_key)) = (smallest_key, largest_key) {
+                tables.push(UserCollectedProperties::build(TableSummary {
+                    smallest_key,
+                    largest_key,
+                    total_size,
+                    total_keys,
+                    min_ts,
+                    max_ts,
+                    num_versions,
+                    num_puts,
+                    num_deletes,
+                    num_rows,
+                }));
+            }
+        }
+
+        Ok(TablePropertiesCollection { tables })
     }
 }
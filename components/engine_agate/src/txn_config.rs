@@ -0,0 +1,55 @@
+// Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
+
+/// How an `AgateWriteBatch` treats write conflicts on its `DeleteRange` ops.
+/// `Optimistic` records the keys each range matched when it was staged and fails the
+/// commit with a conflict error if that set has changed; `Pessimistic` makes no such
+/// check and takes no locks. Transactions the engine opens itself are the same in both
+/// modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxnMode {
+    Optimistic,
+    Pessimistic,
+}
+
+/// Transaction tuning knobs for an `AgateEngine`, set once at construction time via
+/// `AgateEngine::new_with_txn_config` and shared by every transaction the engine opens.
+/// Mirrors the style of `KeyComparator`: a small plain-data config object threaded
+/// through the engine rather than a flag soup of constructor arguments.
+#[derive(Clone, Copy, Debug)]
+pub struct TxnConfig {
+    pub mode: TxnMode,
+    /// Stored with the config but not read by the engine: no transaction takes
+    /// pessimistic locks, and no deadlock is ever detected or reported.
+    pub deadlock_detect: bool,
+    /// Checked by `MiscExt::sync_wal` (and so `KvEngine::sync`), which returns
+    /// immediately whether it is set or not; neither call ever blocks waiting for
+    /// durability.
+    pub allow_write_stall: bool,
+}
+
+impl TxnConfig {
+    pub fn set_deadlock_detect(&mut self, enabled: bool) -> &mut Self {
+        self.deadlock_detect = enabled;
+        self
+    }
+
+    pub fn allow_write_stall(&mut self, enabled: bool) -> &mut Self {
+        self.allow_write_stall = enabled;
+        self
+    }
+
+    pub fn set_mode(&mut self, mode: TxnMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Default for TxnConfig {
+    fn default() -> Self {
+        TxnConfig {
+            mode: TxnMode::Optimistic,
+            deadlock_detect: false,
+            allow_write_stall: true,
+        }
+    }
+}
@@ -1,31 +1,98 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::cmp::Ordering;
+
 use engine_traits::CF_DEFAULT;
 
-// SIMPLE design, just use `DELIMITER` as the delimiter to seperate the name of column family
-// and the original key. If we want to insert a key into a sepecified column family, we will
-// use the format `${CF_NAME}${DELIMITER}${KEY}` to represent the key.
-static DELIMITER: &str = "@!@";
+/// A pluggable order over raw keys, registered once at `AgateEngine::new` and shared by
+/// every iterator the engine hands out. It decides iterator bound checks, the
+/// `delete_range` argument check and `seek_for_prev`'s key-equality test instead of
+/// plain byte comparison. It does not change iteration order: the underlying `agate`
+/// instance stores and walks keys in plain byte order.
+#[derive(Clone, Debug)]
+pub struct KeyComparator {
+    pub name: String,
+    pub cmp: fn(&[u8], &[u8]) -> Ordering,
+    /// Whether two byte-distinct keys may still compare equal under `cmp`. When this is
+    /// true, `seek_for_prev` must fall back to `cmp` rather than a raw byte-equality
+    /// check to decide whether it has already landed on the target key.
+    pub diff_bytes_can_equal: bool,
+}
 
-pub fn add_cf_prefix(key: &[u8], cf_name: Option<String>) -> Vec<u8> {
-    let mut cf_name = match cf_name {
-        Some(cf_name) => cf_name,
-        None => CF_DEFAULT.to_owned(),
-    };
+impl KeyComparator {
+    pub fn keys_equal(&self, a: &[u8], b: &[u8]) -> bool {
+        if self.diff_bytes_can_equal {
+            (self.cmp)(a, b) == Ordering::Equal
+        } else {
+            a == b
+        }
+    }
+}
+
+impl Default for KeyComparator {
+    fn default() -> Self {
+        KeyComparator {
+            name: "bytewise".to_string(),
+            cmp: |a, b| a.cmp(b),
+            diff_bytes_can_equal: false,
+        }
+    }
+}
 
-    cf_name += DELIMITER;
+// Binary-safe column-family key encoding: `[varint(cf_name.len())][cf_name bytes][key
+// bytes]`. The CF name is always plain ASCII chosen by TiKV itself ("default", "write",
+// "lock", ...), so it's fine to UTF-8-validate; the user key that follows is arbitrary
+// binary and is never validated or split on. Since the encoded prefix is constant for a
+// given CF, byte-comparing two encoded keys from the same CF compares their raw user
+// keys, so iteration order and `DeleteRange` bounds are unaffected by the encoding.
+fn write_varint_u32(value: u32, out: &mut Vec<u8>) {
+    let mut value = value;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value != 0 {
+            out.push(byte | 0x80);
+        } else {
+            out.push(byte);
+            break;
+        }
+    }
+}
 
-    let mut cf_name_vec = cf_name.as_bytes();
-    vec![cf_name_vec, key].concat()
+// Returns the decoded value together with how many bytes of `buf` it consumed.
+fn read_varint_u32(buf: &[u8]) -> (u32, usize) {
+    let mut result: u32 = 0;
+    let mut shift = 0;
+
+    for (consumed, &byte) in buf.iter().enumerate() {
+        result |= ((byte & 0x7F) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return (result, consumed + 1);
+        }
+        shift += 7;
+    }
+
+    unreachable!("add_cf_prefix always emits a properly terminated varint");
 }
 
-pub fn get_cf_and_key(key_with_cf: &[u8]) -> (String, Vec<u8>) {
-    let key_with_cf = std::str::from_utf8(key_with_cf).unwrap();
+pub fn add_cf_prefix(key: &[u8], cf_name: Option<String>) -> Vec<u8> {
+    let cf_name = cf_name.unwrap_or_else(|| CF_DEFAULT.to_owned());
+    let cf_bytes = cf_name.as_bytes();
+
+    let mut key_with_cf = Vec::with_capacity(5 + cf_bytes.len() + key.len());
+    write_varint_u32(cf_bytes.len() as u32, &mut key_with_cf);
+    key_with_cf.extend_from_slice(cf_bytes);
+    key_with_cf.extend_from_slice(key);
+    key_with_cf
+}
 
-    let mut key_vec = key_with_cf.split(DELIMITER).collect::<Vec<&str>>();
+pub fn get_cf_and_key(key_with_cf: &[u8]) -> (String, Vec<u8>) {
+    let (cf_len, header_len) = read_varint_u32(key_with_cf);
+    let cf_len = cf_len as usize;
 
-    let cf_name = key_vec.remove(0).to_string();
-    let key = key_vec.concat().as_bytes().to_vec();
+    let cf_name = String::from_utf8(key_with_cf[header_len..header_len + cf_len].to_vec())
+        .expect("CF names are always valid UTF-8");
+    let key = key_with_cf[header_len + cf_len..].to_vec();
 
     (cf_name, key)
 }
@@ -39,11 +106,12 @@ mod tests {
     #[test]
     fn simple_add_cf_prefix() {
         let key = "key".as_bytes();
-        let key_default = "default@!@key".as_bytes();
-        let key_cf = "cf@!@key".as_bytes();
 
-        assert_eq!(&add_cf_prefix(key, None), key_default);
-        assert_eq!(&add_cf_prefix(key, Some("cf".to_string())), key_cf);
+        assert_eq!(&add_cf_prefix(key, None), &[7, b'd', b'e', b'f', b'a', b'u', b'l', b't', b'k', b'e', b'y']);
+        assert_eq!(
+            &add_cf_prefix(key, Some("cf".to_string())),
+            &[2, b'c', b'f', b'k', b'e', b'y']
+        );
     }
 
     #[test]
@@ -61,4 +129,41 @@ mod tests {
         let key_with_cf = add_cf_prefix(key, Some(cf.clone()));
         assert_eq!(get_cf_and_key(&key_with_cf), (cf, key.to_vec()));
     }
+
+    #[test]
+    fn round_trip_binary_key() {
+        // A key containing NUL, non-UTF-8 bytes, and the old string delimiter's bytes
+        // verbatim ("@!@") must round-trip untouched: no UTF-8 validation and no
+        // delimiter splitting should ever be applied to the key itself.
+        let key: &[u8] = &[0x00, 0xFF, 0xC0, 0xC1, b'@', b'!', b'@', 0xFE];
+
+        for cf in [None, Some("write".to_string()), Some("lock".to_string())] {
+            let encoded = add_cf_prefix(key, cf.clone());
+            let (decoded_cf, decoded_key) = get_cf_and_key(&encoded);
+
+            assert_eq!(decoded_cf, cf.unwrap_or_else(|| CF_DEFAULT.to_string()));
+            assert_eq!(decoded_key, key);
+        }
+    }
+
+    #[test]
+    fn same_cf_prefix_preserves_key_order() {
+        let a = add_cf_prefix(b"a", Some("cf".to_string()));
+        let b = add_cf_prefix(b"b", Some("cf".to_string()));
+        let z = add_cf_prefix(&[0xFF], Some("cf".to_string()));
+
+        assert!(a < b);
+        assert!(b < z);
+    }
+
+    #[test]
+    fn different_cf_names_cannot_collide() {
+        // A delimiter-based encoding could confuse a `cf` CF's "@!@key" with a
+        // `"cf@!@key"` stored in the default CF; the length-prefixed encoding can't,
+        // since the CF name's length is recorded up front.
+        let key_in_cf = add_cf_prefix(b"key", Some("cf".to_string()));
+        let literal_key_in_default = add_cf_prefix(b"cf@!@key", None);
+
+        assert_ne!(key_in_cf, literal_key_in_default);
+    }
 }
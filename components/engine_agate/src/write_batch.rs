@@ -8,14 +8,31 @@ use engine_traits::{Mutable, Result, WriteBatch, WriteBatchExt, WriteOptions, CF
 
 use crate::{
     engine::AgateEngine,
+    txn_config::{TxnConfig, TxnMode},
     utils::{add_cf_prefix, get_cf_and_key},
 };
 
+/// Message prefix `write_opt` uses to report an optimistic-mode write conflict.
+///
+/// `engine_traits::Error` has no dedicated `Conflict` variant for a caller to match on,
+/// so this crate stands one up as an `Error::Engine(String)` carrying this prefix;
+/// callers that want to distinguish "retry me" from other engine errors can check
+/// `message.starts_with(CONFLICT_ERROR_PREFIX)`.
+pub const CONFLICT_ERROR_PREFIX: &str = "Conflict: ";
+
+fn conflict_error(detail: impl std::fmt::Display) -> engine_traits::Error {
+    engine_traits::Error::Engine(format!("{CONFLICT_ERROR_PREFIX}{detail}"))
+}
+
 #[derive(Clone)]
 enum WriteBatchOpType {
     Put(Bytes, Bytes, Option<String>),
     Delete(Bytes, Option<String>),
-    DeleteRange(Bytes, Bytes, Option<String>),
+    // The last field is the read set implied by the range scan: the keys the range
+    // matched when this op was staged, recorded in `TxnMode::Optimistic` so `write_opt`
+    // can tell whether another writer touched the range since. `None` in
+    // `TxnMode::Pessimistic`, where no such check is made.
+    DeleteRange(Bytes, Bytes, Option<String>, Option<Vec<Bytes>>),
 }
 
 impl WriteBatchExt for AgateEngine {
@@ -25,7 +42,7 @@ impl WriteBatchExt for AgateEngine {
     const WRITE_BATCH_MAX_KEYS: usize = 128;
 
     fn write_batch(&self) -> Self::WriteBatch {
-        AgateWriteBatch::new(self.agate.clone())
+        self.write_batch_opt(self.txn_config)
     }
 
     fn write_batch_with_cap(&self, cap: usize) -> Self::WriteBatch {
@@ -41,13 +58,22 @@ struct AgateWriteBatchInner {
 
 pub struct AgateWriteBatch {
     agate: Arc<Agate>,
+    txn_config: TxnConfig,
     inner: Mutex<AgateWriteBatchInner>,
 }
 
 impl AgateWriteBatch {
     pub fn new(agate: Arc<Agate>) -> AgateWriteBatch {
+        Self::new_with_txn_config(agate, TxnConfig::default())
+    }
+
+    /// Like `new`, but commits under `txn_config.mode` instead of always defaulting to
+    /// `TxnMode::Optimistic`. `AgateEngine::write_batch`/`write_batch_opt` go through
+    /// this so a batch inherits (or overrides) the engine's own transaction tuning.
+    pub fn new_with_txn_config(agate: Arc<Agate>, txn_config: TxnConfig) -> AgateWriteBatch {
         AgateWriteBatch {
             agate,
+            txn_config,
             inner: Mutex::new(AgateWriteBatchInner {
                 operations: vec![],
                 save_points: vec![],
@@ -58,6 +84,105 @@ impl AgateWriteBatch {
     pub fn get_db(&self) -> Arc<Agate> {
         self.agate.clone()
     }
+
+    /// Marks the current set of staged operations as a nested savepoint, mirroring
+    /// `WriteBatch::set_save_point`. Exposed under this name too so callers modeled on
+    /// RocksDB's transaction bridge (`set_savepoint`/`rollback_to_savepoint`/
+    /// `pop_savepoint`) can stage speculative writes without going through the trait.
+    pub fn set_savepoint(&mut self) {
+        self.set_save_point()
+    }
+
+    /// Discards every operation staged since the last `set_savepoint`, mirroring
+    /// `WriteBatch::rollback_to_save_point`.
+    pub fn rollback_to_savepoint(&mut self) -> Result<()> {
+        self.rollback_to_save_point()
+    }
+
+    /// Drops the most recent savepoint marker without discarding the operations staged
+    /// under it, folding them into the savepoint below (or into the whole batch, if
+    /// none remains), mirroring `WriteBatch::pop_save_point`.
+    pub fn pop_savepoint(&mut self) -> Result<()> {
+        self.pop_save_point()
+    }
+
+    /// Flushes every staged operation in a single `agate` transaction, equivalent to
+    /// `WriteBatch::write`. Pairs with `AgateEngine::begin_txn`, which opens the batch.
+    pub fn commit(&self) -> Result<()> {
+        self.write()
+    }
+
+    /// Discards every staged operation without flushing them, equivalent to `clear`.
+    pub fn rollback(&mut self) {
+        self.clear()
+    }
+
+    /// In `TxnMode::Optimistic`, snapshots the keys a `DeleteRange` currently matches so
+    /// `write_opt` can later tell whether another writer touched the range before this
+    /// batch committed. Returns `None` in `TxnMode::Pessimistic`, where no such check is
+    /// made.
+    fn capture_delete_range_read_set(
+        &self,
+        begin_key: &[u8],
+        end_key: &[u8],
+        cf: &Option<String>,
+    ) -> Option<Vec<Bytes>> {
+        if self.txn_config.mode != TxnMode::Optimistic {
+            return None;
+        }
+
+        let mut txn = self.agate.new_transaction(false);
+        Some(scan_range_keys(&mut txn, begin_key, end_key, cf))
+    }
+}
+
+/// Keys within `cf` in `[begin_key, end_key)` as of `txn`'s read snapshot. Shared by
+/// `write_opt`'s actual scan-and-delete pass and the optimistic-mode read-set capture
+/// in `delete_range`/`delete_range_cf`, so both agree on what counts as "in range".
+fn scan_range_keys(
+    txn: &mut agatedb::Transaction,
+    begin_key: &[u8],
+    end_key: &[u8],
+    cf: &Option<String>,
+) -> Vec<Bytes> {
+    let begin_key = add_cf_prefix(begin_key, cf.clone());
+    let end_key = add_cf_prefix(end_key, cf.clone());
+
+    let mut iter = txn.new_iterator(&IteratorOptions::default());
+    iter.seek(&Bytes::from(begin_key.clone()));
+
+    let is_valid = |iter: &agatedb::Iterator| {
+        if !iter.valid() {
+            return false;
+        }
+
+        let (cf_name, _) = get_cf_and_key(iter.item().key());
+
+        let cf_name_match = match cf {
+            Some(cf) => cf_name == *cf,
+            None => cf_name == CF_DEFAULT,
+        };
+
+        if !cf_name_match {
+            return false;
+        }
+
+        if !begin_key.is_empty() && iter.item().key() < &begin_key[..] {
+            return false;
+        }
+        if !end_key.is_empty() && iter.item().key() >= &end_key[..] {
+            return false;
+        }
+
+        true
+    };
+
+    let mut keys = Vec::new();
+    while is_valid(&iter) {
+        keys.push(Bytes::copy_from_slice(iter.item().key()));
+        iter.next();
+    }
+    keys
 }
 
 impl WriteBatch for AgateWriteBatch {
@@ -81,49 +206,27 @@ impl WriteBatch for AgateWriteBatch {
                     txn.delete(Bytes::from(key))
                         .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
                 }
-                WriteBatchOpType::DeleteRange(begin_key, end_key, cf) => {
+                WriteBatchOpType::DeleteRange(begin_key, end_key, cf, expected_keys) => {
                     if end_key < begin_key {
                         return Err(engine_traits::Error::Engine(
                             "end_key should be equal or greater than begin_key".to_string(),
                         ));
                     }
 
-                    let begin_key = add_cf_prefix(begin_key, cf.clone());
-                    let end_key = add_cf_prefix(end_key, cf.clone());
-
-                    let mut iter = txn.new_iterator(&IteratorOptions::default());
-                    iter.seek(&Bytes::from(begin_key.clone()));
-
-                    let is_valid = |iter: &agatedb::Iterator| {
-                        if !iter.valid() {
-                            return false;
-                        }
-
-                        let (cf_name, _) = get_cf_and_key(iter.item().key());
-
-                        let cf_name_match = match cf {
-                            Some(cf) => cf_name == *cf,
-                            None => cf_name == CF_DEFAULT,
-                        };
-
-                        if !cf_name_match {
-                            return false;
+                    if let Some(expected_keys) = expected_keys {
+                        let mut read_txn = self.agate.new_transaction(false);
+                        let current_keys = scan_range_keys(&mut read_txn, begin_key, end_key, cf);
+                        if &current_keys != expected_keys {
+                            return Err(conflict_error(format!(
+                                "range [{begin_key:?}, {end_key:?}) in cf {cf:?} was \
+                                 written by another writer after this batch staged its delete"
+                            )));
                         }
+                    }
 
-                        if !begin_key.is_empty() && iter.item().key() < &begin_key[..] {
-                            return false;
-                        }
-                        if !end_key.is_empty() && iter.item().key() >= &end_key[..] {
-                            return false;
-                        }
-
-                        true
-                    };
-
-                    while is_valid(&iter) {
-                        txn.delete(Bytes::copy_from_slice(iter.item().key()))
+                    for key in scan_range_keys(&mut txn, begin_key, end_key, cf) {
+                        txn.delete(key)
                             .map_err(|e| engine_traits::Error::Engine(e.to_string()))?;
-                        iter.next();
                     }
                 }
             }
@@ -147,7 +250,7 @@ impl WriteBatch for AgateWriteBatch {
                 WriteBatchOpType::Delete(key, cf) => {
                     key.len() + cf.as_ref().map(|cf| cf.len()).unwrap_or(0)
                 }
-                WriteBatchOpType::DeleteRange(begin_key, end_key, cf) => {
+                WriteBatchOpType::DeleteRange(begin_key, end_key, cf, _) => {
                     begin_key.len() + end_key.len() + cf.as_ref().map(|cf| cf.len()).unwrap_or(0)
                 }
             })
@@ -206,9 +309,19 @@ impl WriteBatch for AgateWriteBatch {
 
     fn merge(&mut self, src: Self) -> Result<()> {
         let mut wb = self.inner.lock().unwrap();
-        let src_wb = src.inner.lock().unwrap();
-
-        wb.operations.extend(src_wb.operations.clone());
+        let mut src_wb = src.inner.lock().unwrap();
+
+        // `src`'s save points are relative to its own operations; shift them past
+        // whatever's already staged on `self` before splicing them in, so
+        // `rollback_to_save_point` still truncates to the right spot post-merge.
+        let offset = wb.operations.len();
+        wb.save_points.extend(
+            src_wb
+                .save_points
+                .iter()
+                .map(|save_point| save_point + offset),
+        );
+        wb.operations.extend(src_wb.operations.drain(..));
         Ok(())
     }
 }
@@ -256,23 +369,30 @@ impl Mutable for AgateWriteBatch {
         Ok(())
     }
     fn delete_range(&mut self, begin_key: &[u8], end_key: &[u8]) -> Result<()> {
+        let expected_keys = self.capture_delete_range_read_set(begin_key, end_key, &None);
+
         let mut wb = self.inner.lock().unwrap();
 
         wb.operations.push(WriteBatchOpType::DeleteRange(
             Bytes::copy_from_slice(begin_key),
             Bytes::copy_from_slice(end_key),
             None,
+            expected_keys,
         ));
 
         Ok(())
     }
     fn delete_range_cf(&mut self, cf: &str, begin_key: &[u8], end_key: &[u8]) -> Result<()> {
+        let cf = Some(cf.to_string());
+        let expected_keys = self.capture_delete_range_read_set(begin_key, end_key, &cf);
+
         let mut wb = self.inner.lock().unwrap();
 
         wb.operations.push(WriteBatchOpType::DeleteRange(
             Bytes::copy_from_slice(begin_key),
             Bytes::copy_from_slice(end_key),
-            Some(cf.to_string()),
+            cf,
+            expected_keys,
         ));
 
         Ok(())
@@ -322,4 +442,79 @@ mod tests {
         wb.clear();
         assert!(!wb.should_write_to_engine());
     }
+
+    #[test]
+    fn test_optimistic_delete_range_conflict_is_reported() {
+        let path = Builder::new()
+            .prefix("test-optimistic-delete-range-conflict")
+            .tempdir()
+            .unwrap();
+
+        let engine = AgateEngine::new(path.path(), vec![]);
+        engine.put(b"k1", b"v1").unwrap();
+
+        let mut wb = engine.write_batch();
+        wb.delete_range(b"k0", b"k9").unwrap();
+
+        // Another writer touches the range after `delete_range` captured its read set,
+        // but before this batch commits.
+        engine.put(b"k2", b"v2").unwrap();
+
+        let err = wb.write().unwrap_err();
+        assert!(err.to_string().starts_with(CONFLICT_ERROR_PREFIX));
+
+        // The conflicting write survives untouched since the batch never committed.
+        assert_eq!(&*engine.get_value(b"k2").unwrap().unwrap(), b"v2");
+    }
+
+    #[test]
+    fn test_pessimistic_delete_range_skips_conflict_check() {
+        let path = Builder::new()
+            .prefix("test-pessimistic-delete-range-skips-conflict-check")
+            .tempdir()
+            .unwrap();
+
+        let mut txn_config = TxnConfig::default();
+        txn_config.set_mode(TxnMode::Pessimistic);
+        let engine =
+            AgateEngine::new_with_txn_config(path.path(), vec![], Default::default(), txn_config);
+        engine.put(b"k1", b"v1").unwrap();
+
+        let mut wb = engine.write_batch();
+        wb.delete_range(b"k0", b"k9").unwrap();
+
+        engine.put(b"k2", b"v2").unwrap();
+
+        wb.write().unwrap();
+        assert!(engine.get_value(b"k1").unwrap().is_none());
+        assert!(engine.get_value(b"k2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_merge_splices_relative_save_points() {
+        let path = Builder::new()
+            .prefix("test-merge-splices-relative-save-points")
+            .tempdir()
+            .unwrap();
+
+        let engine = AgateEngine::new(path.path(), vec![]);
+
+        let mut wb = engine.write_batch();
+        wb.put(b"a1", b"v1").unwrap();
+
+        let mut src = engine.write_batch();
+        src.put(b"b1", b"v1").unwrap();
+        src.set_save_point();
+        src.put(b"b2", b"v2").unwrap();
+
+        wb.merge(src).unwrap();
+        // `src`'s save point sat after its first op; post-merge that's after `wb`'s own
+        // pre-merge op plus `src`'s first op.
+        wb.rollback_to_save_point().unwrap();
+        wb.write().unwrap();
+
+        assert_eq!(&*engine.get_value(b"a1").unwrap().unwrap(), b"v1");
+        assert_eq!(&*engine.get_value(b"b1").unwrap().unwrap(), b"v1");
+        assert!(engine.get_value(b"b2").unwrap().is_none());
+    }
 }